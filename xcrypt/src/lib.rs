@@ -20,11 +20,15 @@
 //! crypt("hello", &setting);
 //! ```
 
+use rand::{RngCore, SeedableRng};
 use std::{
     alloc::{Layout, alloc_zeroed, dealloc, handle_alloc_error},
     ffi::{CStr, CString, c_char, c_ulong},
     fmt, io,
+    ops::RangeInclusive,
 };
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
 
 #[derive(Debug)]
 pub enum Error {
@@ -78,6 +82,15 @@ pub fn crypt_gensalt(
     prefix: Option<&str>,
     count: c_ulong,
     random_bytes: Option<&[u8]>,
+) -> Result<String, Error> {
+    gensalt(prefix, count, random_bytes)
+}
+
+/// The shared implementation behind [`crypt_gensalt`] and [`Crypter::gensalt`].
+fn gensalt(
+    prefix: Option<&str>,
+    count: c_ulong,
+    random_bytes: Option<&[u8]>,
 ) -> Result<String, Error> {
     let c_prefix = prefix
         .map(|s| CString::new(s).map_err(|_| Error::invalid_argument("Prefix contains NULL byte")))
@@ -122,6 +135,8 @@ pub fn crypt_gensalt(
                     88 /* ENOSYS */ | 13 /* EACCESS */ | 5 /* EIO */ => Error::RngNotAvailable,
                     _ => Error::IoError(last_os_error),
                 };
+                #[cfg(feature = "zeroize")]
+                output.zeroize();
                 return Err(error);
             }
         }
@@ -129,7 +144,179 @@ pub fn crypt_gensalt(
         CStr::from_ptr(settings_ptr)
     };
 
-    Ok(c_settings.to_string_lossy().to_string())
+    let settings = c_settings.to_string_lossy().to_string();
+
+    #[cfg(feature = "zeroize")]
+    output.zeroize();
+
+    Ok(settings)
+}
+
+/// A hashing method supported by libxcrypt, identified by the prefix `crypt_gensalt` expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashingMethod {
+    Yescrypt,
+    GostYescrypt,
+    Scrypt,
+    Bcrypt,
+    Sha512,
+    Sha256,
+}
+
+impl HashingMethod {
+    fn prefix(self) -> &'static str {
+        match self {
+            Self::Yescrypt => "$y$",
+            Self::GostYescrypt => "$gy$",
+            Self::Scrypt => "$7$",
+            Self::Bcrypt => "$2b$",
+            Self::Sha512 => "$6$",
+            Self::Sha256 => "$5$",
+        }
+    }
+
+    /// The legal range for the `count` (cost) argument to `crypt_gensalt` when using this method.
+    fn cost_range(self) -> RangeInclusive<c_ulong> {
+        match self {
+            Self::Yescrypt | Self::GostYescrypt => 1..=11,
+            Self::Scrypt => 6..=11,
+            Self::Bcrypt => 4..=31,
+            Self::Sha512 | Self::Sha256 => 1..=999_999_999,
+        }
+    }
+}
+
+/// A builder for the settings string passed to [`crypt`], with the cost validated against the
+/// chosen [`HashingMethod`].
+///
+/// # Examples
+///
+/// ```
+/// use xcrypt::{crypt, GenSaltBuilder, HashingMethod};
+///
+/// let setting = GenSaltBuilder::new(HashingMethod::Yescrypt).cost(5).build().unwrap();
+/// crypt("hello", &setting).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct GenSaltBuilder<'a> {
+    method: HashingMethod,
+    cost: c_ulong,
+    random_bytes: Option<&'a [u8]>,
+}
+
+impl<'a> GenSaltBuilder<'a> {
+    /// Start building a settings string for `method`, with libxcrypt's default cost.
+    pub fn new(method: HashingMethod) -> Self {
+        Self {
+            method,
+            cost: 0,
+            random_bytes: None,
+        }
+    }
+
+    /// Set the cost/hardness parameter.
+    pub fn cost(mut self, cost: c_ulong) -> Self {
+        self.cost = cost;
+        self
+    }
+
+    /// Supply explicit random bytes for the salt, instead of relying on libxcrypt's own RNG.
+    pub fn random_bytes(mut self, random_bytes: &'a [u8]) -> Self {
+        self.random_bytes = Some(random_bytes);
+        self
+    }
+
+    /// Validate the builder's parameters and produce a settings string via [`crypt_gensalt`].
+    pub fn build(self) -> Result<String, Error> {
+        if self.cost != 0 && !self.method.cost_range().contains(&self.cost) {
+            return Err(Error::invalid_argument(&format!(
+                "cost {} is out of range for {:?}",
+                self.cost, self.method
+            )));
+        }
+
+        crypt_gensalt(Some(self.method.prefix()), self.cost, self.random_bytes)
+    }
+}
+
+/// A source of random bytes used to generate a salt for [`crypt_gensalt_with`].
+pub trait RandomSource {
+    /// Fill `buf` with random bytes.
+    fn fill(&mut self, buf: &mut [u8]);
+}
+
+/// A [`RandomSource`] backed by the operating system's cryptographic RNG, via the `rand` crate.
+///
+/// Useful on platforms where libxcrypt's own internal RNG is unavailable (see
+/// [`Error::RngNotAvailable`]), since the salt bytes are then generated in userspace and passed
+/// explicitly to `crypt_gensalt`.
+pub struct OsRandomSource;
+
+impl RandomSource for OsRandomSource {
+    fn fill(&mut self, buf: &mut [u8]) {
+        rand::rng().fill_bytes(buf);
+    }
+}
+
+/// A [`RandomSource`] that deterministically expands a fixed seed into an arbitrary number of
+/// bytes, so that tests can reproduce a specific settings string.
+///
+/// # Examples
+///
+/// ```
+/// use xcrypt::{crypt_gensalt_with, SeededSource};
+///
+/// let mut rng = SeededSource::new(0x1234_5678);
+/// let setting_1 = crypt_gensalt_with(Some("$y$"), 0, &mut rng).unwrap();
+/// let mut rng = SeededSource::new(0x1234_5678);
+/// let setting_2 = crypt_gensalt_with(Some("$y$"), 0, &mut rng).unwrap();
+/// assert_eq!(setting_1, setting_2);
+/// ```
+pub struct SeededSource {
+    rng: rand::rngs::StdRng,
+}
+
+impl SeededSource {
+    /// Create a `SeededSource` that always produces the same byte stream for a given `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: rand::rngs::StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl RandomSource for SeededSource {
+    fn fill(&mut self, buf: &mut [u8]) {
+        self.rng.fill_bytes(buf);
+    }
+}
+
+/// The number of random bytes `crypt_gensalt` needs in order to generate a salt for the hashing
+/// method identified by `prefix`.
+fn salt_bytes_needed(prefix: Option<&str>) -> usize {
+    match prefix {
+        Some("$y$" | "$gy$" | "$7$") => 32,
+        Some("$2a$" | "$2b$" | "$2y$" | "$6$" | "$5$") => 16,
+        Some("$1$") => 6,
+        Some("_") => 3,
+        _ => 16,
+    }
+}
+
+/// Generate the salt bytes required for `prefix`'s hashing method using `rng`, then forward them
+/// to [`crypt_gensalt`] as `random_bytes`.
+///
+/// This allows generating a settings string even on platforms where libxcrypt's own RNG is
+/// unavailable (see [`Error::RngNotAvailable`]), by supplying an [`OsRandomSource`], or
+/// deterministically for tests, by supplying a [`SeededSource`].
+pub fn crypt_gensalt_with<R: RandomSource>(
+    prefix: Option<&str>,
+    count: c_ulong,
+    rng: &mut R,
+) -> Result<String, Error> {
+    let mut salt = vec![0u8; salt_bytes_needed(prefix)];
+    rng.fill(&mut salt);
+    crypt_gensalt(prefix, count, Some(&salt))
 }
 
 /// The `crypt_data` for `crypt_r()`.
@@ -157,49 +344,272 @@ impl CryptData {
     fn as_ptr(&self) -> *mut xcrypt_sys::crypt_data {
         self.ptr.cast::<xcrypt_sys::crypt_data>()
     }
+
+    /// Re-zero the scratch buffer.
+    ///
+    /// Required before each call that reuses a [`CryptData`] that has already been handed to
+    /// `crypt_r`, since only a freshly allocated buffer is guaranteed to be zeroed.
+    fn zero(&self) {
+        unsafe {
+            std::ptr::write_bytes(self.ptr, 0, self.layout.size());
+        }
+    }
 }
 
 impl Drop for CryptData {
     fn drop(&mut self) {
+        self.zero();
         unsafe {
             dealloc(self.ptr, self.layout);
         }
     }
 }
 
+/// A reusable handle that amortizes the 32KiB `crypt_data` scratch allocation used by [`crypt`]
+/// and [`verify`] across many calls, which matters when hashing or verifying many phrases in a
+/// loop (e.g. migrating a shadow database).
+///
+/// # Examples
+///
+/// ```
+/// use xcrypt::{Crypter, HashingMethod, GenSaltBuilder};
+///
+/// let crypter = Crypter::new();
+/// let setting = GenSaltBuilder::new(HashingMethod::Yescrypt).build().unwrap();
+/// for phrase in ["hello", "world"] {
+///     crypter.crypt(phrase, &setting).unwrap();
+/// }
+/// ```
+pub struct Crypter {
+    crypt_data: CryptData,
+}
+
+impl Crypter {
+    /// Allocate a new `Crypter` with its own scratch buffer.
+    pub fn new() -> Self {
+        Self {
+            crypt_data: CryptData::new(),
+        }
+    }
+
+    /// Equivalent to the free function [`crypt`], but reuses this `Crypter`'s scratch buffer
+    /// instead of allocating a fresh one on every call.
+    ///
+    /// Internally, this calls `crypt_r` so that this method can be safely called from multiple
+    /// threads at the same time, as long as each thread uses its own `Crypter`.
+    pub fn crypt(&self, phrase: &str, setting: &str) -> Result<String, Error> {
+        let c_phrase = CString::new(phrase)
+            .map_err(|_| Error::invalid_argument("Phrase contains NULL byte"))?;
+        let c_setting = CString::new(setting)
+            .map_err(|_| Error::invalid_argument("Setting contains NULL byte"))?;
+
+        self.crypt_data.zero();
+
+        let hashed_phrase = unsafe {
+            let hashed_phrase_ptr = xcrypt_sys::crypt_r(
+                c_phrase.as_ptr(),
+                c_setting.as_ptr(),
+                self.crypt_data.as_ptr(),
+            );
+
+            if hashed_phrase_ptr.is_null() {
+                let last_os_error = io::Error::last_os_error();
+                if let Some(errno) = last_os_error.raw_os_error() {
+                    let error = match errno {
+                        22 /* EINVAL */  => Error::invalid_argument("Invalid setting"),
+                        34 /* ERANGE */ => Error::PhraseTooLong,
+                        _ => Error::IoError(last_os_error),
+                    };
+                    #[cfg(feature = "zeroize")]
+                    {
+                        zeroize_cstring(c_phrase);
+                        zeroize_cstring(c_setting);
+                    }
+                    return Err(error);
+                }
+            }
+
+            CStr::from_ptr(hashed_phrase_ptr)
+                .to_string_lossy()
+                .to_string()
+        };
+
+        #[cfg(feature = "zeroize")]
+        {
+            zeroize_cstring(c_phrase);
+            zeroize_cstring(c_setting);
+        }
+
+        // Per crypt(3), crypt_r signals an invalid/unsupported setting not
+        // only via a null return, but also by returning a non-null string
+        // that starts with '*' and matches neither SETTING nor any valid
+        // hashed passphrase.
+        if hashed_phrase.starts_with('*') {
+            return Err(Error::invalid_argument("Invalid setting"));
+        }
+
+        Ok(hashed_phrase)
+    }
+
+    /// Equivalent to the free function [`verify`], but reuses this `Crypter`'s scratch buffer.
+    pub fn verify(&self, phrase: &str, stored_hash: &str) -> Result<bool, Error> {
+        let recomputed_hash = self.crypt(phrase, stored_hash)?;
+        Ok(constant_time_eq(
+            recomputed_hash.as_bytes(),
+            stored_hash.as_bytes(),
+        ))
+    }
+
+    /// Equivalent to [`Crypter::crypt`], but returns a [`Secret`].
+    #[cfg(feature = "zeroize")]
+    pub fn crypt_secret(&self, phrase: &str, setting: &str) -> Result<Secret, Error> {
+        self.crypt(phrase, setting).map(Secret)
+    }
+
+    /// Equivalent to the free function [`crypt_gensalt`].
+    pub fn gensalt(
+        &self,
+        prefix: Option<&str>,
+        count: c_ulong,
+        random_bytes: Option<&[u8]>,
+    ) -> Result<String, Error> {
+        gensalt(prefix, count, random_bytes)
+    }
+}
+
+impl Default for Crypter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Overwrite a [`CString`]'s backing storage with zeroes before it is deallocated.
+#[cfg(feature = "zeroize")]
+fn zeroize_cstring(c_string: CString) {
+    c_string.into_bytes_with_nul().zeroize();
+}
+
+/// A `String` that is zeroed on drop.
+#[cfg(feature = "zeroize")]
+pub struct Secret(String);
+
+#[cfg(feature = "zeroize")]
+impl std::ops::Deref for Secret {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Secret(..)")
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
 /// Irreversibly hash `phrase` for storage in the system password database (shadow(5)) using a
 /// cryptographic hashing method.
 ///
-/// Internally, this calls `crypt_r` so that this function can be safely called from multiple
-/// threads at the same time.
+/// This is a thin wrapper over a temporary [`Crypter`]; prefer [`Crypter::crypt`] directly when
+/// hashing or verifying many phrases, to amortize the scratch buffer allocation.
 pub fn crypt(phrase: &str, setting: &str) -> Result<String, Error> {
-    let c_phrase =
-        CString::new(phrase).map_err(|_| Error::invalid_argument("Phrase contains NULL byte"))?;
+    Crypter::new().crypt(phrase, setting)
+}
+
+/// Equivalent to [`crypt`], but returns a [`Secret`].
+#[cfg(feature = "zeroize")]
+pub fn crypt_secret(phrase: &str, setting: &str) -> Result<Secret, Error> {
+    Crypter::new().crypt_secret(phrase, setting)
+}
+
+/// Check `phrase` against `stored_hash`.
+///
+/// Internally, this calls [`crypt`], passing `stored_hash` as the setting so that the hashing
+/// method, cost parameters, and salt embedded in `stored_hash` are reused. The recomputed hash
+/// is then compared against `stored_hash` in constant time, so that verification does not leak
+/// information about the phrase through timing.
+///
+/// # Examples
+///
+/// ```
+/// use xcrypt::{crypt, crypt_gensalt, verify};
+///
+/// let setting = crypt_gensalt(None, 0, None).unwrap();
+/// let stored_hash = crypt("hello", &setting).unwrap();
+/// assert!(verify("hello", &stored_hash).unwrap());
+/// assert!(!verify("goodbye", &stored_hash).unwrap());
+/// ```
+pub fn verify(phrase: &str, stored_hash: &str) -> Result<bool, Error> {
+    let recomputed_hash = crypt(phrase, stored_hash)?;
+    Ok(constant_time_eq(
+        recomputed_hash.as_bytes(),
+        stored_hash.as_bytes(),
+    ))
+}
+
+/// Compare two byte slices for equality without leaking their contents through timing.
+///
+/// The longer of the two lengths is always used for the comparison loop, even when the lengths
+/// differ, so that a length mismatch does not produce a length oracle.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let len = a.len().max(b.len());
+    let mut diff = 0u8;
+    for i in 0..len {
+        diff |= a.get(i).copied().unwrap_or(0) ^ b.get(i).copied().unwrap_or(0);
+    }
+    diff == 0 && a.len() == b.len()
+}
+
+/// The status of a settings or hash string, as reported by `crypt_checksalt`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaltStatus {
+    /// Valid for the method it encodes.
+    Ok,
+    /// Valid, but the method is deprecated.
+    MethodLegacy,
+    /// Valid, but the method has been disabled by system policy.
+    MethodDisabled,
+    /// Valid, but the cost parameter is too low.
+    TooCheap,
+    /// Not a valid settings or hash string.
+    Invalid,
+}
+
+/// Check the validity and strength of a settings or hash string.
+///
+/// Internally, this calls `crypt_checksalt`.
+pub fn check_salt(setting: &str) -> Result<SaltStatus, Error> {
     let c_setting =
         CString::new(setting).map_err(|_| Error::invalid_argument("Setting contains NULL byte"))?;
 
-    let hashed_phrase = unsafe {
-        // This is dropped when the unsafe block is exited
-        let crypt_data = CryptData::new();
-
-        let hashed_phrase_ptr =
-            xcrypt_sys::crypt_r(c_phrase.as_ptr(), c_setting.as_ptr(), crypt_data.as_ptr());
+    let status = unsafe { xcrypt_sys::crypt_checksalt(c_setting.as_ptr()) };
 
-        if hashed_phrase_ptr.is_null() {
-            let last_os_error = io::Error::last_os_error();
-            if let Some(errno) = last_os_error.raw_os_error() {
-                let error = match errno {
-                    22 /* EINVAL */  => Error::invalid_argument("Invalid setting"),
-                    34 /* ERANGE */ => Error::PhraseTooLong,
-                    _ => Error::IoError(last_os_error),
-                };
-                return Err(error);
-            }
-        }
+    match status {
+        xcrypt_sys::CRYPT_SALT_OK => Ok(SaltStatus::Ok),
+        xcrypt_sys::CRYPT_SALT_METHOD_LEGACY => Ok(SaltStatus::MethodLegacy),
+        xcrypt_sys::CRYPT_SALT_METHOD_DISABLED => Ok(SaltStatus::MethodDisabled),
+        xcrypt_sys::CRYPT_SALT_TOO_CHEAP => Ok(SaltStatus::TooCheap),
+        xcrypt_sys::CRYPT_SALT_INVALID => Ok(SaltStatus::Invalid),
+        _ => Err(Error::invalid_argument(
+            "crypt_checksalt returned an unrecognized status",
+        )),
+    }
+}
 
-        CStr::from_ptr(hashed_phrase_ptr)
-            .to_string_lossy()
-            .to_string()
-    };
-    Ok(hashed_phrase)
+/// Check whether `stored_hash` should be re-hashed with stronger settings the next time a phrase
+/// is successfully [`verify`]ed against it.
+pub fn needs_rehash(stored_hash: &str) -> Result<bool, Error> {
+    match check_salt(stored_hash)? {
+        SaltStatus::MethodLegacy | SaltStatus::MethodDisabled | SaltStatus::TooCheap => Ok(true),
+        SaltStatus::Ok | SaltStatus::Invalid => Ok(false),
+    }
 }
@@ -1,4 +1,7 @@
-use xcrypt::{crypt, crypt_gensalt};
+use xcrypt::{
+    Crypter, GenSaltBuilder, HashingMethod, OsRandomSource, SaltStatus, SeededSource, check_salt,
+    crypt, crypt_gensalt, crypt_gensalt_with, needs_rehash, verify,
+};
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
@@ -36,10 +39,10 @@ fn gensalt_and_crypt() -> Result<()> {
 #[test]
 fn crypt_gensalt_deterministic() -> Result<()> {
     let mut n = 0x1234_5678_9789_0123_5678_9012u128;
-    let mut random_bytes: Vec<i8> = Vec::new();
+    let mut random_bytes: Vec<u8> = Vec::new();
     while n > 9 {
         let rest = n % 10;
-        random_bytes.push(rest as i8);
+        random_bytes.push(rest as u8);
         n /= 10;
     }
     random_bytes.push(n.try_into()?);
@@ -56,3 +59,112 @@ fn crypt_gensalt_random() -> Result<()> {
     assert_ne!(setting_1, setting_2);
     Ok(())
 }
+
+#[test]
+fn verify_accepts_correct_phrase() -> Result<()> {
+    let setting = crypt_gensalt(Some("$y$"), 0, None)?;
+    let stored_hash = crypt("hello", &setting)?;
+    assert!(verify("hello", &stored_hash)?);
+    Ok(())
+}
+
+#[test]
+fn verify_rejects_wrong_phrase() -> Result<()> {
+    let setting = crypt_gensalt(Some("$y$"), 0, None)?;
+    let stored_hash = crypt("hello", &setting)?;
+    assert!(!verify("goodbye", &stored_hash)?);
+    Ok(())
+}
+
+#[test]
+fn verify_propagates_error_for_malformed_hash() {
+    assert!(verify("hello", "$").is_err());
+}
+
+#[test]
+fn check_salt_accepts_strong_hash() -> Result<()> {
+    let setting = crypt_gensalt(Some("$y$"), 0, None)?;
+    let stored_hash = crypt("hello", &setting)?;
+    assert_eq!(check_salt(&stored_hash)?, SaltStatus::Ok);
+    Ok(())
+}
+
+#[test]
+fn check_salt_rejects_malformed_hash() -> Result<()> {
+    assert_eq!(check_salt("$")?, SaltStatus::Invalid);
+    Ok(())
+}
+
+#[test]
+fn needs_rehash_is_false_for_strong_hash() -> Result<()> {
+    let setting = crypt_gensalt(Some("$y$"), 0, None)?;
+    let stored_hash = crypt("hello", &setting)?;
+    assert!(!needs_rehash(&stored_hash)?);
+    Ok(())
+}
+
+#[test]
+fn needs_rehash_is_true_for_legacy_md5crypt_hash() -> Result<()> {
+    let setting = crypt_gensalt(Some("$1$"), 0, None)?;
+    let stored_hash = crypt("hello", &setting)?;
+    assert!(needs_rehash(&stored_hash)?);
+    Ok(())
+}
+
+#[test]
+fn gensalt_builder_builds_valid_setting() -> Result<()> {
+    let setting = GenSaltBuilder::new(HashingMethod::Bcrypt).cost(6).build()?;
+    let hashed_phrase = crypt("hello", &setting)?;
+    assert!(hashed_phrase.starts_with("$2b$"));
+    Ok(())
+}
+
+#[test]
+fn gensalt_builder_rejects_out_of_range_cost() {
+    assert!(
+        GenSaltBuilder::new(HashingMethod::Bcrypt)
+            .cost(100)
+            .build()
+            .is_err()
+    );
+}
+
+#[test]
+fn crypter_reused_across_multiple_calls() -> Result<()> {
+    let crypter = Crypter::new();
+    let setting = crypt_gensalt(Some("$y$"), 0, None)?;
+
+    let hashed_hello = crypter.crypt("hello", &setting)?;
+    let hashed_world = crypter.crypt("world", &setting)?;
+
+    assert_eq!(hashed_hello, crypt("hello", &setting)?);
+    assert_eq!(hashed_world, crypt("world", &setting)?);
+    assert!(crypter.verify("hello", &hashed_hello)?);
+    assert!(!crypter.verify("world", &hashed_hello)?);
+    Ok(())
+}
+
+#[test]
+fn crypt_gensalt_with_seeded_source_is_deterministic() -> Result<()> {
+    let setting_1 = crypt_gensalt_with(Some("$y$"), 0, &mut SeededSource::new(42))?;
+    let setting_2 = crypt_gensalt_with(Some("$y$"), 0, &mut SeededSource::new(42))?;
+    assert_eq!(setting_1, setting_2);
+    Ok(())
+}
+
+#[test]
+fn crypt_gensalt_with_os_random_source_produces_usable_setting() -> Result<()> {
+    let setting = crypt_gensalt_with(Some("$y$"), 0, &mut OsRandomSource)?;
+    let hashed_phrase = crypt("hello", &setting)?;
+    assert!(hashed_phrase.starts_with("$y$"));
+    Ok(())
+}
+
+#[cfg(feature = "zeroize")]
+#[test]
+fn crypt_secret_matches_crypt() -> Result<()> {
+    let setting = crypt_gensalt(Some("$y$"), 0, None)?;
+    let stored_hash = xcrypt::crypt_secret("hello", &setting)?;
+    assert_eq!(&*stored_hash, crypt("hello", &setting)?.as_str());
+    Ok(())
+}